@@ -0,0 +1,321 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use serde::Serialize;
+use tauri::{AppHandle, Manager, Url};
+
+/// Subdirectory of the app cache directory holding downloaded avatars.
+const AVATAR_DIR: &str = "avatars";
+
+/// Extension of the sidecar file storing the ETag / Last-Modified of an
+/// avatar. Its mtime records when the avatar was last validated.
+const VALIDATORS_EXTENSION: &str = "validators";
+
+/// How long a cached avatar is served without asking the CDN again.
+const REVALIDATE_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Cache size `prune_avatar_cache` trims to when no limit is given, and
+/// that is enforced after every new download.
+const DEFAULT_MAX_CACHE_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Larger responses are rejected; Bluesky avatars are well below this.
+const MAX_AVATAR_BYTES: usize = 5 * 1024 * 1024;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PruneResult {
+    /// Number of avatars deleted
+    pub removed: usize,
+    pub remaining_bytes: u64,
+}
+
+/// Downloads an avatar into the app cache directory and returns its local
+/// path, for use with `convertFileSrc` on the frontend.
+///
+/// Files are named after a hash of the URL. A cached avatar is returned
+/// without a request for [`REVALIDATE_AFTER`]; after that it is revalidated
+/// with `If-None-Match` / `If-Modified-Since`, so an unchanged avatar costs a
+/// 304 instead of a download. If revalidation fails, the cached copy is
+/// still returned.
+#[tauri::command]
+pub async fn cache_avatar(app: AppHandle, url: String) -> Result<String, String> {
+    let parsed = Url::parse(url.trim()).map_err(|e| format!("Invalid URL: {e}"))?;
+    if !matches!(parsed.scheme(), "https" | "http") {
+        return Err(format!("Unsupported URL scheme: {}", parsed.scheme()));
+    }
+
+    let dir = avatar_dir(&app)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create avatar cache: {e}"))?;
+    let path = dir.join(cache_key(parsed.as_str()));
+    let validators_path = path.with_extension(VALIDATORS_EXTENSION);
+
+    if path.is_file() {
+        // The avatar's mtime is its last use, which is what pruning goes by
+        touch(&path);
+
+        let last_validated = fs::metadata(&validators_path).and_then(|m| m.modified());
+        let fresh = last_validated
+            .ok()
+            .and_then(|time| time.elapsed().ok())
+            .is_some_and(|age| age < REVALIDATE_AFTER);
+        if !fresh {
+            let validators = fs::read_to_string(&validators_path)
+                .map(|text| parse_validators(&text))
+                .unwrap_or_default();
+            match fetch(parsed.as_str(), &validators).await {
+                Ok(Fetched::NotModified) => touch(&validators_path),
+                Ok(Fetched::Body(bytes, validators)) => store(&path, &bytes, &validators)?,
+                Err(_) => {}
+            }
+        }
+        return Ok(path.to_string_lossy().into_owned());
+    }
+
+    match fetch(parsed.as_str(), &Validators::default()).await? {
+        Fetched::Body(bytes, validators) => store(&path, &bytes, &validators)?,
+        Fetched::NotModified => return Err("Unexpected 304 for an uncached avatar".to_string()),
+    }
+    prune(&dir, DEFAULT_MAX_CACHE_BYTES)
+        .map_err(|e| format!("Failed to prune avatar cache: {e}"))?;
+
+    Ok(path.to_string_lossy().into_owned())
+}
+
+/// Deletes the least recently used avatars until the cache is at most
+/// `max_bytes` (default 50 MiB).
+#[tauri::command]
+pub fn prune_avatar_cache(app: AppHandle, max_bytes: Option<u64>) -> Result<PruneResult, String> {
+    let dir = avatar_dir(&app)?;
+    if !dir.is_dir() {
+        return Ok(PruneResult {
+            removed: 0,
+            remaining_bytes: 0,
+        });
+    }
+
+    prune(&dir, max_bytes.unwrap_or(DEFAULT_MAX_CACHE_BYTES))
+        .map_err(|e| format!("Failed to prune avatar cache: {e}"))
+}
+
+fn avatar_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_cache_dir()
+        .map(|dir| dir.join(AVATAR_DIR))
+        .map_err(|e| format!("Failed to resolve cache directory: {e}"))
+}
+
+fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default()
+    })
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Validators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+enum Fetched {
+    NotModified,
+    Body(Vec<u8>, Validators),
+}
+
+async fn fetch(url: &str, validators: &Validators) -> Result<Fetched, String> {
+    let mut request = client().get(url);
+    if let Some(etag) = &validators.etag {
+        request = request.header(IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = &validators.last_modified {
+        request = request.header(IF_MODIFIED_SINCE, last_modified);
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch avatar: {e}"))?;
+    let status = response.status();
+    if status == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(Fetched::NotModified);
+    }
+    if !status.is_success() {
+        return Err(format!("Avatar request returned {status}"));
+    }
+
+    let header = |name: reqwest::header::HeaderName| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let validators = Validators {
+        etag: header(ETAG),
+        last_modified: header(LAST_MODIFIED),
+    };
+
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to fetch avatar: {e}"))?;
+    if bytes.len() > MAX_AVATAR_BYTES {
+        return Err(format!("Avatar is larger than {MAX_AVATAR_BYTES} bytes"));
+    }
+
+    Ok(Fetched::Body(bytes.to_vec(), validators))
+}
+
+/// Writes the avatar through a temporary file so that a concurrent reader
+/// never sees a partial image.
+fn store(path: &Path, bytes: &[u8], validators: &Validators) -> Result<(), String> {
+    let partial = path.with_extension("partial");
+    fs::write(&partial, bytes)
+        .and_then(|()| fs::rename(&partial, path))
+        .and_then(|()| {
+            fs::write(
+                path.with_extension(VALIDATORS_EXTENSION),
+                format_validators(validators),
+            )
+        })
+        .map_err(|e| format!("Failed to write avatar cache: {e}"))
+}
+
+/// Removes the oldest avatars by mtime until the total is within
+/// `max_bytes`. Sidecar and partial files have an extension and are skipped.
+fn prune(dir: &Path, max_bytes: u64) -> std::io::Result<PruneResult> {
+    let mut avatars = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some() {
+            continue;
+        }
+        let meta = fs::metadata(&path)?;
+        if meta.is_file() {
+            avatars.push((meta.modified()?, meta.len(), path));
+        }
+    }
+    avatars.sort();
+
+    let mut remaining_bytes: u64 = avatars.iter().map(|(_, len, _)| len).sum();
+    let mut removed = 0;
+    for (_, len, path) in avatars {
+        if remaining_bytes <= max_bytes {
+            break;
+        }
+        fs::remove_file(&path)?;
+        let _ = fs::remove_file(path.with_extension(VALIDATORS_EXTENSION));
+        remaining_bytes -= len;
+        removed += 1;
+    }
+
+    Ok(PruneResult {
+        removed,
+        remaining_bytes,
+    })
+}
+
+fn touch(path: &Path) {
+    let _ = fs::File::options()
+        .write(true)
+        .open(path)
+        .and_then(|file| file.set_modified(SystemTime::now()));
+}
+
+/// FNV-1a of the URL as 16 hex digits. Unlike `DefaultHasher` this is stable
+/// across Rust versions, so an upgrade does not orphan the whole cache.
+fn cache_key(url: &str) -> String {
+    let hash = url.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{hash:016x}")
+}
+
+fn parse_validators(text: &str) -> Validators {
+    let mut validators = Validators::default();
+    for line in text.lines() {
+        match line.split_once(": ") {
+            Some(("ETag", value)) => validators.etag = Some(value.to_string()),
+            Some(("Last-Modified", value)) => validators.last_modified = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    validators
+}
+
+fn format_validators(validators: &Validators) -> String {
+    let mut text = String::new();
+    if let Some(etag) = &validators.etag {
+        text.push_str(&format!("ETag: {etag}\n"));
+    }
+    if let Some(last_modified) = &validators.last_modified {
+        text.push_str(&format!("Last-Modified: {last_modified}\n"));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_key_is_stable_hex() {
+        let key = cache_key("https://cdn.bsky.app/img/avatar/plain/did:plc:abc/cid@jpeg");
+        assert_eq!(key.len(), 16);
+        assert!(key.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(cache_key(""), "cbf29ce484222325");
+        assert_ne!(
+            cache_key("https://a.example/1"),
+            cache_key("https://a.example/2")
+        );
+    }
+
+    #[test]
+    fn validators_round_trip() {
+        let validators = Validators {
+            etag: Some("\"abc\"".to_string()),
+            last_modified: Some("Wed, 21 Oct 2015 07:28:00 GMT".to_string()),
+        };
+        assert_eq!(
+            parse_validators(&format_validators(&validators)),
+            validators
+        );
+        assert_eq!(parse_validators(""), Validators::default());
+    }
+
+    #[test]
+    fn prune_removes_least_recently_used_first() {
+        let dir = std::env::temp_dir().join("moodesky-avatar-prune");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let now = SystemTime::now();
+        for (name, age) in [("old", 30), ("mid", 20), ("new", 10)] {
+            let path = dir.join(name);
+            fs::write(&path, [0u8; 100]).unwrap();
+            fs::write(path.with_extension(VALIDATORS_EXTENSION), "ETag: x\n").unwrap();
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .and_then(|file| file.set_modified(now - Duration::from_secs(age)))
+                .unwrap();
+        }
+
+        let result = prune(&dir, 250).unwrap();
+        assert_eq!(result.removed, 1);
+        assert_eq!(result.remaining_bytes, 200);
+        assert!(!dir.join("old").exists());
+        assert!(!dir.join("old.validators").exists());
+        assert!(dir.join("mid").exists() && dir.join("new").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,105 @@
+use std::path::PathBuf;
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+/// Backend initialization state, checked by the frontend right after startup.
+///
+/// Each subsystem is reported separately so the UI can tell which part of
+/// the backend is broken instead of failing on the first error.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackendHealth {
+    pub version: String,
+    /// The directory tauri-plugin-sql opens SQLite databases from. This does
+    /// not open a connection.
+    pub database_dir: DirStatus,
+    /// The directory tauri-plugin-store persists to.
+    pub store_dir: DirStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DirStatus {
+    /// Exists and is writable
+    Ready,
+    /// Does not exist yet, but the nearest existing ancestor is a writable
+    /// directory, so the plugin can create it (normal on first launch)
+    NotCreated,
+    /// Could not be resolved, is not a directory, or cannot be written
+    Unavailable,
+}
+
+/// Read-only probe: nothing is created or written. A directory the plugins
+/// have not created yet is reported as [`DirStatus::NotCreated`] rather than
+/// as a failure.
+#[tauri::command]
+pub fn health_check(app: AppHandle) -> Result<BackendHealth, String> {
+    let path = app.path();
+
+    Ok(BackendHealth {
+        version: app.package_info().version.to_string(),
+        database_dir: dir_status(path.app_config_dir()),
+        store_dir: dir_status(path.app_data_dir()),
+    })
+}
+
+fn dir_status(dir: tauri::Result<PathBuf>) -> DirStatus {
+    let Ok(dir) = dir else {
+        return DirStatus::Unavailable;
+    };
+
+    match std::fs::metadata(&dir) {
+        Ok(meta) if is_writable_dir(&meta) => DirStatus::Ready,
+        Ok(_) => DirStatus::Unavailable,
+        Err(_) => {
+            let creatable = dir
+                .ancestors()
+                .skip(1)
+                .find_map(|ancestor| std::fs::metadata(ancestor).ok())
+                .is_some_and(|meta| is_writable_dir(&meta));
+            if creatable {
+                DirStatus::NotCreated
+            } else {
+                DirStatus::Unavailable
+            }
+        }
+    }
+}
+
+/// Checks the read-only permission flag, which is as far as a probe can go
+/// without writing a file.
+fn is_writable_dir(meta: &std::fs::Metadata) -> bool {
+    meta.is_dir() && !meta.permissions().readonly()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn status(dir: &Path) -> DirStatus {
+        dir_status(Ok(dir.to_path_buf()))
+    }
+
+    #[test]
+    fn existing_directory_is_ready() {
+        assert_eq!(status(&std::env::temp_dir()), DirStatus::Ready);
+    }
+
+    #[test]
+    fn missing_directory_under_writable_parent_is_not_created() {
+        let dir = std::env::temp_dir().join("moodesky-health-missing/nested");
+        assert_eq!(status(&dir), DirStatus::NotCreated);
+    }
+
+    #[test]
+    fn file_in_place_of_directory_is_unavailable() {
+        let file = std::env::temp_dir().join("moodesky-health-file");
+        std::fs::write(&file, b"").unwrap();
+        assert_eq!(status(&file), DirStatus::Unavailable);
+        assert_eq!(status(&file.join("child")), DirStatus::Unavailable);
+        std::fs::remove_file(&file).unwrap();
+    }
+}
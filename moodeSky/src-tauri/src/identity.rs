@@ -0,0 +1,292 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// Public AppView endpoint that answers com.atproto.identity.resolveHandle
+/// without authentication.
+const RESOLVE_HANDLE_SERVICE: &str = "https://public.api.bsky.app";
+
+const PLC_DIRECTORY: &str = "https://plc.directory";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Why a handle or DID could not be resolved. Serialized as
+/// `{ "kind": "...", "message": "..." }` so the UI can branch on `kind`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", content = "message", rename_all = "camelCase")]
+pub enum IdentityError {
+    /// The handle or DID is syntactically invalid or uses an unsupported method
+    InvalidIdentifier(String),
+    /// The identifier is well-formed but nothing is registered for it
+    NotFound(String),
+    /// The resolver could not be reached or answered with a server error
+    Network(String),
+    /// A DID document was returned but is malformed or has no PDS endpoint
+    InvalidDocument(String),
+}
+
+/// The parts of a DID document moodeSky needs to talk to an account's PDS.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DidDocument {
+    pub did: String,
+    /// Handle claimed by the document (`at://` entry of `alsoKnownAs`)
+    pub handle: Option<String>,
+    /// `serviceEndpoint` of the `#atproto_pds` service
+    pub pds_endpoint: String,
+}
+
+/// Resolves a handle to its DID via com.atproto.identity.resolveHandle.
+#[tauri::command]
+pub async fn resolve_handle(handle: String) -> Result<String, IdentityError> {
+    resolve_handle_to_did(&handle).await
+}
+
+/// Fetches the DID document for a `did:plc` (PLC directory) or `did:web`
+/// (`/.well-known/did.json`) identifier and extracts the PDS endpoint, which
+/// is what an account's `service_url` should be set to.
+#[tauri::command]
+pub async fn resolve_did(did: String) -> Result<DidDocument, IdentityError> {
+    let url = did_document_url(&did)?;
+    let body = get(&url, &did).await?;
+    parse_did_document(&did, &body)
+}
+
+pub(crate) async fn resolve_handle_to_did(handle: &str) -> Result<String, IdentityError> {
+    let handle = handle.trim().trim_start_matches('@').to_ascii_lowercase();
+    if !is_plausible_handle(&handle) {
+        return Err(IdentityError::InvalidIdentifier(format!(
+            "Invalid handle: {handle}"
+        )));
+    }
+
+    #[derive(Deserialize)]
+    struct ResolveHandleOutput {
+        did: String,
+    }
+
+    let url =
+        format!("{RESOLVE_HANDLE_SERVICE}/xrpc/com.atproto.identity.resolveHandle?handle={handle}");
+    let body = get(&url, &handle).await?;
+    let output: ResolveHandleOutput = serde_json::from_str(&body)
+        .map_err(|e| IdentityError::Network(format!("Unexpected resolveHandle response: {e}")))?;
+    Ok(output.did)
+}
+
+fn client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default()
+    })
+}
+
+/// GETs `url` and maps HTTP failures onto [`IdentityError`]. resolveHandle
+/// reports unknown handles as 400, the PLC directory and did:web as 404.
+async fn get(url: &str, identifier: &str) -> Result<String, IdentityError> {
+    let response = client()
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| IdentityError::Network(e.to_string()))?;
+
+    let status = response.status();
+    if status == reqwest::StatusCode::NOT_FOUND || status == reqwest::StatusCode::BAD_REQUEST {
+        return Err(IdentityError::NotFound(format!(
+            "{identifier} could not be resolved"
+        )));
+    }
+    if !status.is_success() {
+        return Err(IdentityError::Network(format!(
+            "Resolver returned {status} for {identifier}"
+        )));
+    }
+
+    response
+        .text()
+        .await
+        .map_err(|e| IdentityError::Network(e.to_string()))
+}
+
+fn did_document_url(did: &str) -> Result<String, IdentityError> {
+    if let Some(id) = did.strip_prefix("did:plc:") {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(IdentityError::InvalidIdentifier(format!(
+                "Invalid DID: {did}"
+            )));
+        }
+        return Ok(format!("{PLC_DIRECTORY}/{did}"));
+    }
+
+    if let Some(host) = did.strip_prefix("did:web:") {
+        // atproto only allows hostname-level did:web, so no path segments
+        if !is_plausible_handle(host) {
+            return Err(IdentityError::InvalidIdentifier(format!(
+                "Unsupported did:web: {did}"
+            )));
+        }
+        return Ok(format!("https://{host}/.well-known/did.json"));
+    }
+
+    Err(IdentityError::InvalidIdentifier(format!(
+        "Unsupported DID method: {did}"
+    )))
+}
+
+fn parse_did_document(did: &str, body: &str) -> Result<DidDocument, IdentityError> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct RawDocument {
+        id: String,
+        #[serde(default)]
+        also_known_as: Vec<String>,
+        #[serde(default)]
+        service: Vec<RawService>,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct RawService {
+        id: String,
+        #[serde(rename = "type")]
+        kind: String,
+        service_endpoint: serde_json::Value,
+    }
+
+    let document: RawDocument = serde_json::from_str(body)
+        .map_err(|e| IdentityError::InvalidDocument(format!("Malformed DID document: {e}")))?;
+
+    if document.id != did {
+        return Err(IdentityError::InvalidDocument(format!(
+            "DID document is for {}, expected {did}",
+            document.id
+        )));
+    }
+
+    let pds_endpoint = document
+        .service
+        .iter()
+        .find(|s| s.id.ends_with("#atproto_pds") && s.kind == "AtprotoPersonalDataServer")
+        .and_then(|s| s.service_endpoint.as_str())
+        .filter(|endpoint| endpoint.starts_with("https://") || endpoint.starts_with("http://"))
+        .ok_or_else(|| IdentityError::InvalidDocument(format!("{did} has no PDS endpoint")))?
+        .trim_end_matches('/')
+        .to_string();
+
+    let handle = document
+        .also_known_as
+        .iter()
+        .find_map(|aka| aka.strip_prefix("at://"))
+        .map(str::to_string);
+
+    Ok(DidDocument {
+        did: document.id,
+        handle,
+        pds_endpoint,
+    })
+}
+
+/// Cheap syntax check before hitting the network: at least two labels of
+/// ASCII letters, digits and hyphens.
+fn is_plausible_handle(handle: &str) -> bool {
+    let labels: Vec<&str> = handle.split('.').collect();
+    labels.len() >= 2
+        && handle.len() <= 253
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOCUMENT: &str = r##"{
+        "id": "did:plc:ewvi7nxzyoun6zhxrhs64oiz",
+        "alsoKnownAs": ["at://atproto.com"],
+        "service": [{
+            "id": "#atproto_pds",
+            "type": "AtprotoPersonalDataServer",
+            "serviceEndpoint": "https://enoki.us-east.host.bsky.network/"
+        }]
+    }"##;
+
+    #[test]
+    fn plc_and_web_dids_map_to_document_urls() {
+        assert_eq!(
+            did_document_url("did:plc:ewvi7nxzyoun6zhxrhs64oiz").unwrap(),
+            "https://plc.directory/did:plc:ewvi7nxzyoun6zhxrhs64oiz"
+        );
+        assert_eq!(
+            did_document_url("did:web:pds.example.com").unwrap(),
+            "https://pds.example.com/.well-known/did.json"
+        );
+    }
+
+    #[test]
+    fn unsupported_dids_are_invalid_identifiers() {
+        for did in [
+            "did:key:z6Mk",
+            "did:web:example.com:user:alice",
+            "did:plc:",
+            "alice",
+        ] {
+            assert!(
+                matches!(
+                    did_document_url(did),
+                    Err(IdentityError::InvalidIdentifier(_))
+                ),
+                "{did}"
+            );
+        }
+    }
+
+    #[test]
+    fn document_yields_pds_endpoint_and_handle() {
+        let document = parse_did_document("did:plc:ewvi7nxzyoun6zhxrhs64oiz", DOCUMENT).unwrap();
+        assert_eq!(
+            document,
+            DidDocument {
+                did: "did:plc:ewvi7nxzyoun6zhxrhs64oiz".to_string(),
+                handle: Some("atproto.com".to_string()),
+                pds_endpoint: "https://enoki.us-east.host.bsky.network".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn document_for_another_did_is_invalid() {
+        assert!(matches!(
+            parse_did_document("did:plc:someoneelse", DOCUMENT),
+            Err(IdentityError::InvalidDocument(_))
+        ));
+    }
+
+    #[test]
+    fn document_without_pds_is_invalid() {
+        let body = r#"{"id": "did:web:example.com", "service": []}"#;
+        assert!(matches!(
+            parse_did_document("did:web:example.com", body),
+            Err(IdentityError::InvalidDocument(_))
+        ));
+        assert!(matches!(
+            parse_did_document("did:web:example.com", "not json"),
+            Err(IdentityError::InvalidDocument(_))
+        ));
+    }
+
+    #[test]
+    fn handle_syntax_is_checked_before_resolving() {
+        assert!(is_plausible_handle("alice.bsky.social"));
+        assert!(!is_plausible_handle("alice"));
+        assert!(!is_plausible_handle("-bad.example"));
+        assert!(!is_plausible_handle("a b.example"));
+    }
+}
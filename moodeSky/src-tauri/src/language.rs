@@ -0,0 +1,226 @@
+use whatlang::{Info, Script};
+
+use crate::richtext;
+
+/// app.bsky.feed.post accepts at most three languages.
+const MAX_LANGS: usize = 3;
+
+/// Share of a post's letters a script must cover before that part of the
+/// text is detected on its own. Kept low because CJK text needs far fewer
+/// characters than Latin text to say the same thing.
+const MIN_SCRIPT_SHARE: f64 = 0.2;
+
+/// Guesses BCP-47 language codes for a post from its text.
+///
+/// The dominant language comes first. A secondary language is added when a
+/// different script covers a significant share of the text, so short mixed
+/// posts (Japanese with an English sentence, say) get both codes. When no
+/// detection is reliable, `default_langs` (the user's configured posting
+/// languages) is returned instead.
+#[tauri::command]
+pub fn detect_post_language(
+    text: String,
+    default_langs: Option<Vec<String>>,
+) -> Result<Vec<String>, String> {
+    let langs = detect_langs(&text);
+    if langs.is_empty() {
+        Ok(default_langs.unwrap_or_default())
+    } else {
+        Ok(langs)
+    }
+}
+
+pub(crate) fn detect_langs(text: &str) -> Vec<String> {
+    let prose = strip_facets(text);
+    let mut langs = Vec::new();
+
+    if let Some(info) = whatlang::detect(&prose).filter(Info::is_reliable) {
+        push_lang(&mut langs, &info);
+    }
+
+    let segments = script_segments(&prose);
+    let total_letters: usize = segments.iter().map(|s| s.letters).sum();
+    for segment in &segments {
+        if langs.len() >= MAX_LANGS {
+            break;
+        }
+        if (segment.letters as f64) < total_letters as f64 * MIN_SCRIPT_SHARE {
+            continue;
+        }
+        if let Some(info) = whatlang::detect(&segment.text).filter(Info::is_reliable) {
+            push_lang(&mut langs, &info);
+        }
+    }
+
+    langs
+}
+
+struct ScriptSegment {
+    script: Script,
+    text: String,
+    letters: usize,
+}
+
+/// Groups whitespace-separated words by script, in order of first appearance.
+fn script_segments(text: &str) -> Vec<ScriptSegment> {
+    let mut segments: Vec<ScriptSegment> = Vec::new();
+
+    for word in text.split_whitespace() {
+        let Some(script) = whatlang::detect_script(word) else {
+            continue;
+        };
+        let letters = word.chars().filter(|c| c.is_alphabetic()).count();
+        match segments.iter_mut().find(|s| s.script == script) {
+            Some(segment) => {
+                segment.text.push(' ');
+                segment.text.push_str(word);
+                segment.letters += letters;
+            }
+            None => segments.push(ScriptSegment {
+                script,
+                text: word.to_string(),
+                letters,
+            }),
+        }
+    }
+
+    segments
+}
+
+/// Blanks out mentions, links and hashtags, which are not prose and skew
+/// detection towards English.
+fn strip_facets(text: &str) -> String {
+    let mut prose = String::with_capacity(text.len());
+    let mut cursor = 0;
+    for facet in richtext::detect_facets(text) {
+        prose.push_str(&text[cursor..facet.index.byte_start]);
+        prose.push(' ');
+        cursor = facet.index.byte_end;
+    }
+    prose.push_str(&text[cursor..]);
+    prose
+}
+
+fn push_lang(langs: &mut Vec<String>, info: &Info) {
+    let code = bcp47_code(info.lang().code()).to_string();
+    if !langs.contains(&code) && langs.len() < MAX_LANGS {
+        langs.push(code);
+    }
+}
+
+/// Maps whatlang's ISO 639-3 codes to the two-letter codes Bluesky uses,
+/// keeping the three-letter code where no two-letter one exists.
+fn bcp47_code(iso639_3: &str) -> &str {
+    match iso639_3 {
+        "afr" => "af",
+        "aka" => "ak",
+        "amh" => "am",
+        "ara" => "ar",
+        "aze" => "az",
+        "bel" => "be",
+        "ben" => "bn",
+        "bul" => "bg",
+        "cat" => "ca",
+        "ces" => "cs",
+        "cmn" => "zh",
+        "dan" => "da",
+        "deu" => "de",
+        "ell" => "el",
+        "eng" => "en",
+        "epo" => "eo",
+        "est" => "et",
+        "fin" => "fi",
+        "fra" => "fr",
+        "guj" => "gu",
+        "heb" => "he",
+        "hin" => "hi",
+        "hrv" => "hr",
+        "hun" => "hu",
+        "hye" => "hy",
+        "ind" => "id",
+        "ita" => "it",
+        "jav" => "jv",
+        "jpn" => "ja",
+        "kan" => "kn",
+        "kat" => "ka",
+        "khm" => "km",
+        "kor" => "ko",
+        "lat" => "la",
+        "lav" => "lv",
+        "lit" => "lt",
+        "mal" => "ml",
+        "mar" => "mr",
+        "mkd" => "mk",
+        "mya" => "my",
+        "nep" => "ne",
+        "nld" => "nl",
+        "nob" => "no",
+        "ori" => "or",
+        "pan" => "pa",
+        "pes" => "fa",
+        "pol" => "pl",
+        "por" => "pt",
+        "ron" => "ro",
+        "rus" => "ru",
+        "sin" => "si",
+        "slk" => "sk",
+        "slv" => "sl",
+        "sna" => "sn",
+        "spa" => "es",
+        "srp" => "sr",
+        "swe" => "sv",
+        "tam" => "ta",
+        "tel" => "te",
+        "tgl" => "tl",
+        "tha" => "th",
+        "tuk" => "tk",
+        "tur" => "tr",
+        "ukr" => "uk",
+        "urd" => "ur",
+        "uzb" => "uz",
+        "vie" => "vi",
+        "yid" => "yi",
+        "zul" => "zu",
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_japanese() {
+        assert_eq!(
+            detect_langs("今日はとても良い天気なので、公園まで散歩に行きました。"),
+            vec!["ja"]
+        );
+    }
+
+    #[test]
+    fn detects_both_languages_in_mixed_post() {
+        let langs = detect_langs(
+            "今日はとても良い天気なので、みんなで近くの公園まで散歩に行きました。 \
+             The weather was wonderful and everyone was outside enjoying the sunshine.",
+        );
+        assert!(langs.contains(&"ja".to_string()), "{langs:?}");
+        assert!(langs.contains(&"en".to_string()), "{langs:?}");
+    }
+
+    #[test]
+    fn links_and_mentions_do_not_count_as_english() {
+        assert_eq!(
+            detect_langs("@alice.bsky.social ありがとうございます！ https://example.com/thanks"),
+            vec!["ja"]
+        );
+    }
+
+    #[test]
+    fn falls_back_to_default_langs_when_unreliable() {
+        assert_eq!(
+            detect_post_language("👍 https://example.com".into(), Some(vec!["ja".into()])),
+            Ok(vec!["ja".to_string()])
+        );
+        assert_eq!(detect_post_language(String::new(), None), Ok(vec![]));
+    }
+}
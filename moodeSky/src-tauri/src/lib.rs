@@ -1,8 +1,10 @@
-// Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
-#[tauri::command]
-fn greet(name: &str) -> String {
-    format!("Hello, {}! You've been greeted from Rust!", name)
-}
+mod avatar;
+mod health;
+mod identity;
+mod language;
+mod link_card;
+mod post;
+mod richtext;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -12,7 +14,19 @@ pub fn run() {
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_dialog::init())
-        .invoke_handler(tauri::generate_handler![greet])
+        .invoke_handler(tauri::generate_handler![
+            avatar::cache_avatar,
+            avatar::prune_avatar_cache,
+            health::health_check,
+            identity::resolve_did,
+            identity::resolve_handle,
+            language::detect_post_language,
+            link_card::generate_fallback_title,
+            post::validate_post,
+            richtext::parse_facets,
+            richtext::parse_markdown_facets,
+            richtext::sanitize_post_text
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
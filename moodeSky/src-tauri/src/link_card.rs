@@ -0,0 +1,284 @@
+use tauri::Url;
+
+/// Path segments that carry no meaning for a reader and are skipped when
+/// building a title from the URL.
+const IGNORED_SEGMENTS: &[&str] = &["index", "index.html", "index.htm", "index.php", "amp"];
+
+/// File extensions stripped from the last path segment.
+const STRIPPED_EXTENSIONS: &[&str] = &[".html", ".htm", ".php", ".aspx", ".asp", ".jsp"];
+
+/// Longest reference name accepted between `&` and `;` (`#x10FFFF`).
+const MAX_ENTITY_LENGTH: usize = 8;
+
+/// Named character references commonly found in page titles.
+const NAMED_ENTITIES: &[(&str, char)] = &[
+    ("amp", '&'),
+    ("lt", '<'),
+    ("gt", '>'),
+    ("quot", '"'),
+    ("apos", '\''),
+    ("nbsp", ' '),
+    ("ndash", '–'),
+    ("mdash", '—'),
+    ("hellip", '…'),
+    ("middot", '·'),
+    ("bull", '•'),
+    ("laquo", '«'),
+    ("raquo", '»'),
+    ("lsquo", '‘'),
+    ("rsquo", '’'),
+    ("ldquo", '“'),
+    ("rdquo", '”'),
+    ("copy", '©'),
+    ("reg", '®'),
+    ("trade", '™'),
+];
+
+/// Builds a readable link card title when OGP metadata could not be fetched.
+///
+/// The page `<title>` is preferred when the caller already has the HTML;
+/// otherwise the title is derived from the last path segments (breadcrumb)
+/// and the domain, e.g. `https://example.com/blog/my-first-post` becomes
+/// `Blog › My first post | example.com`.
+#[tauri::command]
+pub fn generate_fallback_title(url: String, html: Option<String>) -> Result<String, String> {
+    if let Some(title) = html.as_deref().and_then(extract_html_title) {
+        return Ok(title);
+    }
+
+    let parsed = Url::parse(url.trim()).map_err(|e| format!("Invalid URL: {e}"))?;
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| "URL has no host".to_string())?;
+    let domain = host.strip_prefix("www.").unwrap_or(host);
+
+    let breadcrumb: Vec<String> = parsed
+        .path_segments()
+        .map(|segments| {
+            segments
+                .filter(|s| !s.is_empty())
+                .filter(|s| !IGNORED_SEGMENTS.contains(&s.to_ascii_lowercase().as_str()))
+                // Dates and numeric IDs do not read as titles
+                .filter(|s| !s.chars().all(|c| c.is_ascii_digit()))
+                .map(humanize_segment)
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let tail = &breadcrumb[breadcrumb.len().saturating_sub(2)..];
+    if tail.is_empty() {
+        Ok(domain.to_string())
+    } else {
+        Ok(format!("{} | {}", tail.join(" › "), domain))
+    }
+}
+
+/// Extracts the text of the document `<title>`, with entities decoded and
+/// whitespace collapsed. Returns `None` when absent or empty.
+fn extract_html_title(html: &str) -> Option<String> {
+    // ASCII lowercasing keeps byte offsets identical to `html`
+    let lower = html.to_ascii_lowercase();
+    let open = find_document_title(&lower)?;
+    let content_start = open + lower[open..].find('>')? + 1;
+    let content_end = content_start + lower[content_start..].find("</title")?;
+
+    let title = decode_entities(&html[content_start..content_end])
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if title.is_empty() {
+        None
+    } else {
+        Some(title)
+    }
+}
+
+/// Finds the first `<title>` that is not inside an inline `<svg>`, whose
+/// titles are tooltips for the graphic rather than the page title.
+fn find_document_title(lower: &str) -> Option<usize> {
+    let mut from = 0;
+    while let Some(offset) = lower[from..].find("<title") {
+        let open = from + offset;
+        from = open + "<title".len();
+
+        let is_element = matches!(
+            lower.as_bytes().get(from),
+            Some(b'>' | b' ' | b'\t' | b'\n' | b'\r')
+        );
+        // Inside an svg when the last `<svg` comes after the last `</svg`
+        let before = &lower[..open];
+        let in_svg = before.rfind("</svg") < before.rfind("<svg");
+        if is_element && !in_svg {
+            return Some(open);
+        }
+    }
+    None
+}
+
+/// Decodes named and numeric character references in a single pass, so
+/// that `&amp;lt;` becomes `&lt;` rather than `<`. Unknown or malformed
+/// references are kept as written.
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        decoded.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let reference = rest[1..]
+            .find(';')
+            .filter(|&end| end > 0 && end <= MAX_ENTITY_LENGTH)
+            .and_then(|end| decode_entity(&rest[1..=end]).map(|c| (c, end + 2)));
+        match reference {
+            Some((c, len)) => {
+                decoded.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Decodes the name between `&` and `;`, e.g. `amp`, `#39` or `#x2014`.
+fn decode_entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        // Checked up front because parse and from_str_radix accept a `+`
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) if !hex.is_empty() && hex.bytes().all(|b| b.is_ascii_hexdigit()) => {
+                u32::from_str_radix(hex, 16).ok()?
+            }
+            None if !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()) => {
+                number.parse().ok()?
+            }
+            _ => return None,
+        };
+        return char::from_u32(code).filter(|c| *c != '\0');
+    }
+
+    NAMED_ENTITIES
+        .iter()
+        .find(|(entity, _)| *entity == name)
+        .map(|(_, c)| *c)
+}
+
+/// Turns a URL path segment such as `my-first-post.html` into `My first post`.
+fn humanize_segment(segment: &str) -> String {
+    let decoded = percent_decode(segment);
+    let lower = decoded.to_ascii_lowercase();
+    let stem = STRIPPED_EXTENSIONS
+        .iter()
+        .find(|ext| lower.ends_with(*ext))
+        .map_or(decoded.as_str(), |ext| {
+            &decoded[..decoded.len() - ext.len()]
+        });
+
+    let words = stem
+        .split(['-', '_', '+'])
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut chars = words.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn percent_decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = &bytes[i + 1..i + 3];
+            // from_str_radix alone would also accept a leading `+`
+            if hex.iter().all(u8::is_ascii_hexdigit) {
+                let hex = std::str::from_utf8(hex).unwrap_or_default();
+                if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                    decoded.push(byte);
+                    i += 3;
+                    continue;
+                }
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn title(url: &str, html: Option<&str>) -> String {
+        generate_fallback_title(url.to_string(), html.map(str::to_string)).unwrap()
+    }
+
+    #[test]
+    fn html_title_is_preferred() {
+        let html = "<html><head><title>\n  Hello   World\n</title></head></html>";
+        assert_eq!(title("https://example.com/a", Some(html)), "Hello World");
+    }
+
+    #[test]
+    fn named_and_numeric_entities_are_decoded() {
+        let html = "<title>Tom &amp; Jerry &mdash; &#8220;Cats&#x201D; &copy; &amp;lt;</title>";
+        assert_eq!(
+            title("https://example.com", Some(html)),
+            "Tom & Jerry — “Cats” © &lt;"
+        );
+    }
+
+    #[test]
+    fn unknown_entities_are_kept() {
+        assert_eq!(
+            decode_entities("AT&T &bogus; &#xZZ; &"),
+            "AT&T &bogus; &#xZZ; &"
+        );
+    }
+
+    #[test]
+    fn svg_titles_are_skipped() {
+        let html = "<svg><title>Logo</title></svg><head><title>Page</title></head>";
+        assert_eq!(title("https://example.com", Some(html)), "Page");
+    }
+
+    #[test]
+    fn svg_only_title_falls_back_to_url() {
+        let html = "<body><svg><title>Logo</title></svg></body>";
+        assert_eq!(title("https://example.com", Some(html)), "example.com");
+    }
+
+    #[test]
+    fn breadcrumb_uses_last_two_segments() {
+        assert_eq!(
+            title("https://www.example.com/blog/2024/my-first-post.html", None),
+            "Blog › My first post | example.com"
+        );
+    }
+
+    #[test]
+    fn root_url_falls_back_to_domain() {
+        assert_eq!(
+            title("https://www.example.com/index.html", None),
+            "example.com"
+        );
+        assert_eq!(title("https://example.com", None), "example.com");
+    }
+
+    #[test]
+    fn percent_decoding_requires_hex_digits() {
+        assert_eq!(percent_decode("caf%C3%A9"), "café");
+        assert_eq!(percent_decode("a%+1b"), "a%+1b");
+        assert_eq!(percent_decode("100%"), "100%");
+    }
+}
@@ -0,0 +1,241 @@
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::richtext;
+
+/// Maximum post length in graphemes, as enforced by app.bsky.feed.post.
+const MAX_GRAPHEMES: usize = 300;
+
+/// Maximum post length in UTF-8 bytes, also enforced by app.bsky.feed.post.
+/// Reached before the grapheme limit by text heavy in ZWJ emoji sequences.
+const MAX_TEXT_BYTES: usize = 3000;
+
+/// Maximum number of images in app.bsky.embed.images.
+const MAX_IMAGES: usize = 4;
+
+/// Characters that render as nothing, or as blank space without being
+/// `char::is_whitespace` (Braille blank, Hangul fillers). Text made only of
+/// these and whitespace counts as empty.
+const INVISIBLE_CHARS: &[char] = &[
+    '\u{00AD}', '\u{034F}', '\u{061C}', '\u{115F}', '\u{1160}', '\u{180E}', '\u{200C}', '\u{200D}',
+    '\u{200E}', '\u{200F}', '\u{2800}', '\u{3164}', '\u{FFA0}',
+];
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostPayload {
+    pub text: String,
+    #[serde(default)]
+    pub images: Vec<ImageAttachment>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageAttachment {
+    #[serde(default)]
+    pub alt: String,
+}
+
+/// Result of checking a post before it is sent.
+///
+/// `errors` block sending; `warnings` are shown to the user but the post can
+/// still go out, so the compose button is enabled whenever `can_post` is true.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PostValidation {
+    pub can_post: bool,
+    pub grapheme_count: usize,
+    /// Negative when the text is over the limit
+    pub remaining: i64,
+    pub facet_count: usize,
+    pub errors: Vec<PostIssue>,
+    pub warnings: Vec<PostIssue>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "code", rename_all = "camelCase")]
+pub enum PostIssue {
+    /// Neither text nor media
+    Empty,
+    /// Over the grapheme limit
+    TooLong {
+        max: usize,
+        actual: usize,
+    },
+    /// Over the byte limit
+    TooManyBytes {
+        max: usize,
+        actual: usize,
+    },
+    TooManyImages {
+        max: usize,
+        actual: usize,
+    },
+    /// Image at `index` has no alt text
+    MissingAlt {
+        index: usize,
+    },
+    /// Control or invisible characters were stripped from the text
+    TextSanitized {
+        removed: usize,
+    },
+}
+
+#[tauri::command]
+pub fn validate_post(payload: PostPayload) -> Result<PostValidation, String> {
+    let sanitized = richtext::sanitize(&payload.text);
+    let grapheme_count = sanitized.text.graphemes(true).count();
+    let facet_count = richtext::detect_facets(&sanitized.text).len();
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    if sanitized.removed > 0 {
+        warnings.push(PostIssue::TextSanitized {
+            removed: sanitized.removed,
+        });
+    }
+    if is_blank(&sanitized.text) && payload.images.is_empty() {
+        errors.push(PostIssue::Empty);
+    }
+    if grapheme_count > MAX_GRAPHEMES {
+        errors.push(PostIssue::TooLong {
+            max: MAX_GRAPHEMES,
+            actual: grapheme_count,
+        });
+    }
+    if sanitized.text.len() > MAX_TEXT_BYTES {
+        errors.push(PostIssue::TooManyBytes {
+            max: MAX_TEXT_BYTES,
+            actual: sanitized.text.len(),
+        });
+    }
+    if payload.images.len() > MAX_IMAGES {
+        errors.push(PostIssue::TooManyImages {
+            max: MAX_IMAGES,
+            actual: payload.images.len(),
+        });
+    }
+    for (index, image) in payload.images.iter().enumerate() {
+        if image.alt.trim().is_empty() {
+            warnings.push(PostIssue::MissingAlt { index });
+        }
+    }
+
+    Ok(PostValidation {
+        can_post: errors.is_empty(),
+        grapheme_count,
+        remaining: MAX_GRAPHEMES as i64 - grapheme_count as i64,
+        facet_count,
+        errors,
+        warnings,
+    })
+}
+
+fn is_blank(text: &str) -> bool {
+    text.chars().all(|c| {
+        c.is_whitespace()
+            || INVISIBLE_CHARS.contains(&c)
+            // Variation selectors
+            || ('\u{FE00}'..='\u{FE0F}').contains(&c)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(text: &str, alts: &[&str]) -> PostPayload {
+        PostPayload {
+            text: text.to_string(),
+            images: alts
+                .iter()
+                .map(|alt| ImageAttachment {
+                    alt: alt.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn plain_post_is_valid() {
+        let result = validate_post(payload("こんにちは @alice.bsky.social", &[])).unwrap();
+        assert!(result.can_post);
+        assert_eq!(result.grapheme_count, 24);
+        assert_eq!(result.remaining, 276);
+        assert_eq!(result.facet_count, 1);
+        assert!(result.errors.is_empty());
+        assert!(result.warnings.is_empty());
+    }
+
+    #[test]
+    fn empty_post_is_an_error() {
+        let result = validate_post(payload("  \n ", &[])).unwrap();
+        assert!(!result.can_post);
+        assert!(matches!(result.errors[..], [PostIssue::Empty]));
+    }
+
+    #[test]
+    fn invisible_only_post_is_an_error() {
+        for text in ["\u{200D}", "\u{200E}\u{2800}", " \u{3164}\n\u{FE0F} "] {
+            let result = validate_post(payload(text, &[])).unwrap();
+            assert!(!result.can_post, "{text:?}");
+            assert!(matches!(result.errors[..], [PostIssue::Empty]), "{text:?}");
+        }
+    }
+
+    #[test]
+    fn image_only_post_is_not_empty() {
+        let result = validate_post(payload("", &["a cat"])).unwrap();
+        assert!(result.can_post);
+    }
+
+    #[test]
+    fn over_grapheme_limit_is_an_error() {
+        let result = validate_post(payload(&"あ".repeat(301), &[])).unwrap();
+        assert!(!result.can_post);
+        assert_eq!(result.remaining, -1);
+        assert!(matches!(
+            result.errors[..],
+            [PostIssue::TooLong {
+                max: 300,
+                actual: 301
+            }]
+        ));
+    }
+
+    #[test]
+    fn over_byte_limit_is_an_error_within_grapheme_limit() {
+        let family = "👨\u{200D}👩\u{200D}👧";
+        let result = validate_post(payload(&family.repeat(200), &[])).unwrap();
+        assert_eq!(result.grapheme_count, 200);
+        assert!(!result.can_post);
+        assert!(matches!(
+            result.errors[..],
+            [PostIssue::TooManyBytes {
+                max: 3000,
+                actual: 3600
+            }]
+        ));
+    }
+
+    #[test]
+    fn too_many_images_is_an_error() {
+        let result = validate_post(payload("photos", &["1", "2", "3", "4", "5"])).unwrap();
+        assert!(!result.can_post);
+        assert!(matches!(
+            result.errors[..],
+            [PostIssue::TooManyImages { max: 4, actual: 5 }]
+        ));
+    }
+
+    #[test]
+    fn missing_alt_is_a_warning() {
+        let result = validate_post(payload("photos", &["a cat", " "])).unwrap();
+        assert!(result.can_post);
+        assert!(result.errors.is_empty());
+        assert!(matches!(
+            result.warnings[..],
+            [PostIssue::MissingAlt { index: 1 }]
+        ));
+    }
+}
@@ -0,0 +1,804 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::identity;
+
+/// Maximum tag length in characters, excluding the leading `#`.
+const MAX_TAG_LENGTH: usize = 64;
+
+/// Characters that terminate a hashtag in addition to whitespace
+/// (soft hyphen, word joiner, zero-width characters, keycap enclosure).
+const TAG_TERMINATORS: &[char] = &[
+    '\u{00AD}', '\u{2060}', '\u{200A}', '\u{200B}', '\u{200C}', '\u{200D}', '\u{20E2}',
+];
+
+/// Full-width and CJK punctuation trimmed from the end of hashtags alongside
+/// ASCII punctuation.
+const CJK_PUNCTUATION: &[char] = &[
+    '、', '。', '，', '．', '！', '？', '：', '；', '「', '」', '『', '』', '（', '）', '【', '】',
+    '…', '・', '“', '”', '‘', '’',
+];
+
+/// Invisible characters with no legitimate use in a post: zero-width space,
+/// word joiner, BOM, and the bidirectional embedding/override/isolate controls
+/// used to visually reorder text.
+const STRIPPED_FORMAT_CHARS: &[char] = &[
+    '\u{200B}', '\u{2060}', '\u{FEFF}', '\u{202A}', '\u{202B}', '\u{202C}', '\u{202D}', '\u{202E}',
+    '\u{2066}', '\u{2067}', '\u{2068}', '\u{2069}',
+];
+
+/// Implicit directional marks (LRM, RLM, ALM). Legitimate in Hebrew and
+/// Arabic text, so only runs of them are collapsed.
+const DIRECTIONAL_MARKS: &[char] = &['\u{200E}', '\u{200F}', '\u{061C}'];
+
+/// Zero-width joiner and non-joiner. Needed inside emoji sequences and in
+/// scripts where they control letter joining or conjuncts; anywhere else they
+/// only serve to split words so filters miss them.
+const JOINERS: &[char] = &['\u{200C}', '\u{200D}'];
+
+/// `app.bsky.richtext.facet` with UTF-8 byte offsets into the post text.
+#[derive(Debug, Clone, Serialize)]
+pub struct Facet {
+    pub index: ByteSlice,
+    pub features: Vec<FacetFeature>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ByteSlice {
+    pub byte_start: usize,
+    pub byte_end: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "$type")]
+pub enum FacetFeature {
+    /// The handle is kept for preview; `did` stays unset unless the caller
+    /// asked for mentions to be resolved and the handle resolved.
+    #[serde(rename = "app.bsky.richtext.facet#mention")]
+    Mention {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        did: Option<String>,
+        handle: String,
+    },
+    #[serde(rename = "app.bsky.richtext.facet#link")]
+    Link { uri: String },
+    #[serde(rename = "app.bsky.richtext.facet#tag")]
+    Tag { tag: String },
+}
+
+/// Post text after [`sanitize_post_text`]. `removed` counts dropped characters
+/// so the caller can warn that the text was altered; CRLF to LF conversion is
+/// not counted.
+#[derive(Debug, Clone, Serialize)]
+pub struct SanitizedText {
+    pub text: String,
+    pub removed: usize,
+}
+
+/// Strips control characters and normalizes zero-width and bidi characters
+/// that are abused to disguise spam.
+///
+/// Must run before facet detection: facet byte offsets are only valid for the
+/// exact text that ends up in the record.
+#[tauri::command]
+pub fn sanitize_post_text(text: String) -> Result<SanitizedText, String> {
+    Ok(sanitize(&text))
+}
+
+pub(crate) fn sanitize(text: &str) -> SanitizedText {
+    let normalized = text.replace("\r\n", "\n");
+    let mut sanitized = String::with_capacity(normalized.len());
+    let mut removed = 0;
+    let chars: Vec<char> = normalized.chars().collect();
+    let mut prev = None;
+
+    // For each position, the next character that is neither a joiner nor
+    // stripped: what a joiner there would end up joining to
+    let mut following = vec![None; chars.len()];
+    for i in (0..chars.len().saturating_sub(1)).rev() {
+        let next = chars[i + 1];
+        following[i] = if JOINERS.contains(&next) || is_stripped(next) {
+            following[i + 1]
+        } else {
+            Some(next)
+        };
+    }
+
+    for (i, &c) in chars.iter().enumerate() {
+        let keep = match c {
+            _ if is_stripped(c) => false,
+            // One joiner per run, and only between characters it can join
+            _ if JOINERS.contains(&c) => {
+                !prev.is_some_and(|p| JOINERS.contains(&p))
+                    && prev.zip(following[i]).is_some_and(|(p, n)| can_join(p, n))
+            }
+            _ if DIRECTIONAL_MARKS.contains(&c) => {
+                !prev.is_some_and(|p| DIRECTIONAL_MARKS.contains(&p))
+            }
+            _ => true,
+        };
+        if keep {
+            sanitized.push(c);
+            prev = Some(c);
+        } else {
+            removed += 1;
+        }
+    }
+
+    SanitizedText {
+        text: sanitized,
+        removed,
+    }
+}
+
+fn is_stripped(c: char) -> bool {
+    (c.is_control() && c != '\n' && c != '\t') || STRIPPED_FORMAT_CHARS.contains(&c)
+}
+
+fn can_join(prev: char, next: char) -> bool {
+    (is_emoji(prev) && is_emoji(next)) || (is_joining_script(prev) && is_joining_script(next))
+}
+
+/// Emoji, skin tone modifiers and VS16, which may precede a ZWJ in
+/// sequences such as `❤️‍🔥`.
+fn is_emoji(c: char) -> bool {
+    matches!(c,
+        '\u{00A9}' | '\u{00AE}' | '\u{203C}' | '\u{2049}' | '\u{2122}' | '\u{2139}'
+        | '\u{2194}'..='\u{21AA}'
+        | '\u{2300}'..='\u{23FF}'
+        | '\u{2600}'..='\u{27BF}'
+        | '\u{2B00}'..='\u{2BFF}'
+        | '\u{FE0F}'
+        | '\u{1F000}'..='\u{1FAFF}'
+        | '\u{E0020}'..='\u{E007F}')
+}
+
+/// Scripts in which ZWJ/ZWNJ legitimately change rendering: cursive joining
+/// (Arabic, Syriac, N'Ko, Mongolian) and conjunct forming (Indic, Myanmar,
+/// Khmer).
+fn is_joining_script(c: char) -> bool {
+    matches!(c,
+        '\u{0600}'..='\u{07FF}'
+        | '\u{0860}'..='\u{08FF}'
+        | '\u{0900}'..='\u{0DFF}'
+        | '\u{1000}'..='\u{109F}'
+        | '\u{1780}'..='\u{18AF}'
+        | '\u{A8E0}'..='\u{A8FF}'
+        | '\u{FB50}'..='\u{FDFF}'
+        | '\u{FE70}'..='\u{FEFC}')
+}
+
+/// Sanitized post text together with the facets detected in it.
+///
+/// The offsets in `facets` are only valid for `text`, never for the input
+/// the caller passed in, which is why the two are returned together.
+#[derive(Debug, Clone, Serialize)]
+pub struct ParsedText {
+    pub text: String,
+    pub facets: Vec<Facet>,
+    /// Characters dropped by sanitization, see [`SanitizedText`]
+    pub removed: usize,
+}
+
+/// Sanitizes `text` and detects mentions, links and hashtags in the result.
+///
+/// Offsets are UTF-8 byte positions as required by the AT Protocol, so text
+/// containing emoji or Japanese maps correctly onto the record. Intended for
+/// the compose preview as well as for building the post record.
+///
+/// With `resolve_dids`, each mentioned handle is resolved to its DID. Handles
+/// that fail to resolve keep `did` unset rather than failing the whole call,
+/// so the preview still shows them.
+#[tauri::command]
+pub async fn parse_facets(text: String, resolve_dids: Option<bool>) -> Result<ParsedText, String> {
+    let mut parsed = parse_text(&text);
+    if resolve_dids.unwrap_or(false) {
+        resolve_mentions(&mut parsed.facets).await;
+    }
+    Ok(parsed)
+}
+
+pub(crate) fn parse_text(text: &str) -> ParsedText {
+    let sanitized = sanitize(text);
+    let facets = detect_facets(&sanitized.text);
+
+    ParsedText {
+        text: sanitized.text,
+        facets,
+        removed: sanitized.removed,
+    }
+}
+
+/// Converts Markdown-style `[label](https://…)` links into link facets over
+/// the label, then detects mentions, links and hashtags in the resulting text
+/// as [`parse_facets`] does. Only the label is kept in `text`.
+///
+/// Facets must not overlap, so a detected facet that overlaps a Markdown link
+/// is dropped in favour of the link the user wrote explicitly.
+#[tauri::command]
+pub async fn parse_markdown_facets(
+    markdown: String,
+    resolve_dids: Option<bool>,
+) -> Result<ParsedText, String> {
+    let mut parsed = parse_markdown(&markdown);
+    if resolve_dids.unwrap_or(false) {
+        resolve_mentions(&mut parsed.facets).await;
+    }
+    Ok(parsed)
+}
+
+pub(crate) fn parse_markdown(markdown: &str) -> ParsedText {
+    let sanitized = sanitize(markdown);
+    let mut text = String::with_capacity(sanitized.text.len());
+    let mut links = Vec::new();
+    let mut rest = sanitized.text.as_str();
+
+    while let Some(open) = rest.find('[') {
+        let Some((label, uri, len)) = markdown_link(&rest[open..]) else {
+            text.push_str(&rest[..=open]);
+            rest = &rest[open + 1..];
+            continue;
+        };
+
+        text.push_str(&rest[..open]);
+        let byte_start = text.len();
+        text.push_str(label);
+        links.push(Facet {
+            index: ByteSlice {
+                byte_start,
+                byte_end: text.len(),
+            },
+            features: vec![FacetFeature::Link {
+                uri: uri.to_string(),
+            }],
+        });
+        rest = &rest[open + len..];
+    }
+    text.push_str(rest);
+
+    let mut facets: Vec<Facet> = detect_facets(&text)
+        .into_iter()
+        .filter(|facet| !links.iter().any(|link| overlaps(&facet.index, &link.index)))
+        .collect();
+    facets.extend(links);
+    facets.sort_by_key(|facet| facet.index.byte_start);
+
+    ParsedText {
+        text,
+        facets,
+        removed: sanitized.removed,
+    }
+}
+
+/// Parses `[label](uri)` at the start of `s` and returns the label, the URI
+/// and the byte length of the whole construct. Only single-line labels and
+/// http(s) URIs are accepted; parentheses inside the URI must be balanced.
+fn markdown_link(s: &str) -> Option<(&str, &str, usize)> {
+    let close = s.find(']')?;
+    let label = &s[1..close];
+    if label.trim().is_empty() || label.contains(['[', '\n']) {
+        return None;
+    }
+
+    let target = s[close + 1..].strip_prefix('(')?;
+    let mut depth = 0;
+    let mut uri_len = None;
+    for (i, c) in target.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => {
+                uri_len = Some(i);
+                break;
+            }
+            ')' => depth -= 1,
+            _ if !is_url_char(c) => return None,
+            _ => {}
+        }
+    }
+    let uri = &target[..uri_len?];
+
+    let scheme_len = if uri.starts_with("https://") {
+        "https://".len()
+    } else if uri.starts_with("http://") {
+        "http://".len()
+    } else {
+        return None;
+    };
+    if uri.len() <= scheme_len {
+        return None;
+    }
+
+    // `[` + label + `](` + uri + `)`
+    Some((label, uri, close + 2 + uri.len() + 1))
+}
+
+fn overlaps(a: &ByteSlice, b: &ByteSlice) -> bool {
+    a.byte_start < b.byte_end && b.byte_start < a.byte_end
+}
+
+/// Fills in `did` for every mention, resolving each distinct handle once.
+pub(crate) async fn resolve_mentions(facets: &mut [Facet]) {
+    let mut resolved: HashMap<String, Option<String>> = HashMap::new();
+
+    for feature in facets.iter_mut().flat_map(|f| f.features.iter_mut()) {
+        let FacetFeature::Mention { did, handle } = feature else {
+            continue;
+        };
+        if did.is_some() {
+            continue;
+        }
+        if !resolved.contains_key(handle.as_str()) {
+            let result = identity::resolve_handle_to_did(handle).await.ok();
+            resolved.insert(handle.clone(), result);
+        }
+        did.clone_from(&resolved[handle.as_str()]);
+    }
+}
+
+pub(crate) fn detect_facets(text: &str) -> Vec<Facet> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut facets = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let (start, c) = chars[i];
+        let prev = i.checked_sub(1).map(|p| chars[p].1);
+        let at_boundary = prev.is_none_or(|p| p.is_whitespace() || p == '(');
+
+        let detected = if c == '@' && at_boundary {
+            detect_mention(text, start)
+        } else if (c == '#' || c == '＃') && prev.is_none_or(char::is_whitespace) {
+            detect_tag(text, start, c)
+        } else if c == 'h' && at_boundary {
+            detect_link(text, start)
+        } else {
+            None
+        };
+
+        match detected {
+            Some(facet) => {
+                let end = facet.index.byte_end;
+                facets.push(facet);
+                while i < chars.len() && chars[i].0 < end {
+                    i += 1;
+                }
+            }
+            None => i += 1,
+        }
+    }
+
+    facets
+}
+
+fn detect_mention(text: &str, at: usize) -> Option<Facet> {
+    let rest = &text[at + 1..];
+    let len = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '-'))
+        .unwrap_or(rest.len());
+    let handle = rest[..len].trim_end_matches(['.', '-']);
+
+    if !is_valid_handle(handle) {
+        return None;
+    }
+
+    Some(Facet {
+        index: ByteSlice {
+            byte_start: at,
+            byte_end: at + 1 + handle.len(),
+        },
+        features: vec![FacetFeature::Mention {
+            did: None,
+            handle: handle.to_ascii_lowercase(),
+        }],
+    })
+}
+
+fn detect_link(text: &str, start: usize) -> Option<Facet> {
+    let rest = &text[start..];
+    let scheme_len = if rest.starts_with("https://") {
+        "https://".len()
+    } else if rest.starts_with("http://") {
+        "http://".len()
+    } else {
+        return None;
+    };
+
+    let len = rest.find(|c: char| !is_url_char(c)).unwrap_or(rest.len());
+    let mut uri = &rest[..len];
+    loop {
+        let trimmed = uri.trim_end_matches(['.', ',', ';', ':', '!', '?', '"', '\'']);
+        // A closing paren only belongs to the URL when it also opens one
+        let trimmed = match trimmed.strip_suffix(')') {
+            Some(inner) if !inner.contains('(') => inner,
+            _ => trimmed,
+        };
+        if trimmed.len() == uri.len() {
+            break;
+        }
+        uri = trimmed;
+    }
+
+    if uri.len() <= scheme_len {
+        return None;
+    }
+
+    Some(Facet {
+        index: ByteSlice {
+            byte_start: start,
+            byte_end: start + uri.len(),
+        },
+        features: vec![FacetFeature::Link {
+            uri: uri.to_string(),
+        }],
+    })
+}
+
+fn detect_tag(text: &str, hash: usize, hash_char: char) -> Option<Facet> {
+    let rest = &text[hash + hash_char.len_utf8()..];
+    // `#` followed by VS16 is the keycap emoji, not a tag
+    if rest.starts_with('\u{FE0F}') {
+        return None;
+    }
+
+    let len = rest
+        .find(|c: char| c.is_whitespace() || TAG_TERMINATORS.contains(&c))
+        .unwrap_or(rest.len());
+    let tag = rest[..len].trim_end_matches(is_punctuation);
+
+    if tag.chars().count() > MAX_TAG_LENGTH
+        || !tag.chars().any(|c| !c.is_numeric() && !is_punctuation(c))
+    {
+        return None;
+    }
+
+    Some(Facet {
+        index: ByteSlice {
+            byte_start: hash,
+            byte_end: hash + hash_char.len_utf8() + tag.len(),
+        },
+        features: vec![FacetFeature::Tag {
+            tag: tag.to_string(),
+        }],
+    })
+}
+
+fn is_valid_handle(handle: &str) -> bool {
+    let labels: Vec<&str> = handle.split('.').collect();
+    labels.len() >= 2
+        && handle.len() <= 253
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+        })
+        && labels
+            .last()
+            .is_some_and(|tld| tld.starts_with(|c: char| c.is_ascii_alphabetic()))
+}
+
+/// Characters allowed in a URL as typed in a post: the RFC 3986 set plus
+/// non-ASCII letters and digits for IRIs such as Japanese Wikipedia paths.
+/// Whitespace, CJK punctuation and emoji end the URL.
+fn is_url_char(c: char) -> bool {
+    if c.is_ascii() {
+        c.is_ascii_alphanumeric() || "-._~:/?#[]@!$&'()*+,;=%".contains(c)
+    } else {
+        c.is_alphanumeric() && !CJK_PUNCTUATION.contains(&c)
+    }
+}
+
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation() || CJK_PUNCTUATION.contains(&c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(text: &str) -> Vec<(usize, usize, &str)> {
+        detect_facets(text)
+            .iter()
+            .map(|f| {
+                let (start, end) = (f.index.byte_start, f.index.byte_end);
+                (start, end, &text[start..end])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn offsets_are_utf8_bytes_after_multibyte_text() {
+        let text = "日本語 @alice.bsky.social 🎉 https://example.com #タグ";
+        let mention = text.find('@').unwrap();
+        let link = text.find("https").unwrap();
+        let tag = text.find('#').unwrap();
+
+        assert_eq!(mention, 10);
+        assert_eq!(
+            spans(text),
+            vec![
+                (mention, mention + 18, "@alice.bsky.social"),
+                (link, link + 19, "https://example.com"),
+                (tag, tag + 7, "#タグ"),
+            ]
+        );
+    }
+
+    #[test]
+    fn mention_handle_is_lowercased_and_trailing_dot_dropped() {
+        let facets = detect_facets("こんにちは @Alice.Bsky.Social.");
+        assert_eq!(facets.len(), 1);
+        match &facets[0].features[0] {
+            FacetFeature::Mention { did, handle } => {
+                assert_eq!(did, &None);
+                assert_eq!(handle, "alice.bsky.social");
+            }
+            other => panic!("unexpected feature: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn invalid_handles_are_not_mentions() {
+        assert!(detect_facets("@localhost @-bad.example @foo.123").is_empty());
+    }
+
+    #[test]
+    fn link_trailing_punctuation_is_trimmed() {
+        assert_eq!(
+            spans("see https://example.com/a, and https://example.com/b!?")
+                .iter()
+                .map(|s| s.2)
+                .collect::<Vec<_>>(),
+            vec!["https://example.com/a", "https://example.com/b"]
+        );
+    }
+
+    #[test]
+    fn link_parentheses_are_kept_only_when_balanced() {
+        assert_eq!(
+            spans("(https://example.com/x) https://en.wikipedia.org/wiki/Rust_(language)")
+                .iter()
+                .map(|s| s.2)
+                .collect::<Vec<_>>(),
+            vec![
+                "https://example.com/x",
+                "https://en.wikipedia.org/wiki/Rust_(language)"
+            ]
+        );
+    }
+
+    #[test]
+    fn link_ends_at_cjk_punctuation_and_non_url_characters() {
+        assert_eq!(
+            spans("見て https://example.com/a。すごい https://example.com/b」 https://example.com/c🎉")
+                .iter()
+                .map(|s| s.2)
+                .collect::<Vec<_>>(),
+            vec![
+                "https://example.com/a",
+                "https://example.com/b",
+                "https://example.com/c"
+            ]
+        );
+    }
+
+    #[test]
+    fn link_keeps_non_ascii_path() {
+        assert_eq!(
+            spans("https://ja.wikipedia.org/wiki/東京都、")[0].2,
+            "https://ja.wikipedia.org/wiki/東京都"
+        );
+    }
+
+    #[test]
+    fn bare_scheme_is_not_a_link() {
+        assert!(detect_facets("http:// https://.").is_empty());
+    }
+
+    #[test]
+    fn keycap_emoji_is_not_a_tag() {
+        assert!(detect_facets("#\u{FE0F}\u{20E3} 番号").is_empty());
+    }
+
+    #[test]
+    fn full_width_hash_starts_a_tag() {
+        let text = "今日は ＃ラーメン！";
+        let start = text.find('＃').unwrap();
+        assert_eq!(spans(text), vec![(start, start + 15, "＃ラーメン")]);
+        match &detect_facets(text)[0].features[0] {
+            FacetFeature::Tag { tag } => assert_eq!(tag, "ラーメン"),
+            other => panic!("unexpected feature: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn numeric_only_tags_are_ignored() {
+        assert!(detect_facets("#123 #２０２４ #1.5").is_empty());
+        assert_eq!(spans("#2024年")[0].2, "#2024年");
+    }
+
+    #[test]
+    fn tag_requires_whitespace_before_hash() {
+        assert!(detect_facets("a#tag").is_empty());
+    }
+
+    #[test]
+    fn parsed_offsets_match_the_sanitized_text() {
+        let parsed = parse_text("\u{200B}\u{200B}hi @alice.bsky.social");
+        let index = &parsed.facets[0].index;
+
+        assert_eq!(parsed.text, "hi @alice.bsky.social");
+        assert_eq!(parsed.removed, 2);
+        assert_eq!((index.byte_start, index.byte_end), (3, 21));
+        assert_eq!(
+            &parsed.text[index.byte_start..index.byte_end],
+            "@alice.bsky.social"
+        );
+    }
+
+    fn facet_spans(parsed: &ParsedText) -> Vec<&str> {
+        parsed
+            .facets
+            .iter()
+            .map(|f| &parsed.text[f.index.byte_start..f.index.byte_end])
+            .collect()
+    }
+
+    #[test]
+    fn markdown_link_keeps_label_and_points_facet_at_it() {
+        let parsed = parse_markdown("詳しくは[こちら](https://example.com/a)を見て #お知らせ");
+        assert_eq!(parsed.text, "詳しくはこちらを見て #お知らせ");
+        assert_eq!(facet_spans(&parsed), vec!["こちら", "#お知らせ"]);
+        match &parsed.facets[0].features[0] {
+            FacetFeature::Link { uri } => assert_eq!(uri, "https://example.com/a"),
+            other => panic!("unexpected feature: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn markdown_link_wins_over_overlapping_facets() {
+        let parsed = parse_markdown(
+            "[@alice.bsky.social](https://example.com/alice) [https://a.example](https://b.example) @bob.bsky.social",
+        );
+        assert_eq!(
+            parsed.text,
+            "@alice.bsky.social https://a.example @bob.bsky.social"
+        );
+        assert_eq!(
+            facet_spans(&parsed),
+            vec![
+                "@alice.bsky.social",
+                "https://a.example",
+                "@bob.bsky.social"
+            ]
+        );
+        assert!(matches!(
+            parsed.facets[0].features[0],
+            FacetFeature::Link { .. }
+        ));
+        assert!(matches!(
+            parsed.facets[1].features[0],
+            FacetFeature::Link { ref uri } if uri == "https://b.example"
+        ));
+        assert!(matches!(
+            parsed.facets[2].features[0],
+            FacetFeature::Mention { .. }
+        ));
+    }
+
+    #[test]
+    fn markdown_link_allows_balanced_parentheses() {
+        let parsed = parse_markdown("[Rust](https://en.wikipedia.org/wiki/Rust_(language)).");
+        assert_eq!(parsed.text, "Rust.");
+        match &parsed.facets[0].features[0] {
+            FacetFeature::Link { uri } => {
+                assert_eq!(uri, "https://en.wikipedia.org/wiki/Rust_(language)")
+            }
+            other => panic!("unexpected feature: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn malformed_markdown_links_are_left_as_text() {
+        for markdown in [
+            "[a] (https://example.com)",
+            "[a](javascript:alert(1))",
+            "[](https://example.com)",
+            "[a](https://example.com",
+            "[a](https:// example.com)",
+        ] {
+            assert_eq!(parse_markdown(markdown).text, markdown);
+        }
+        // The bare URL is still picked up by ordinary link detection
+        assert_eq!(
+            facet_spans(&parse_markdown("[a] (https://example.com)")),
+            vec!["https://example.com"]
+        );
+    }
+
+    #[test]
+    fn markdown_offsets_match_the_sanitized_text() {
+        let parsed = parse_markdown("\u{200B}🎉[x](https://example.com)");
+        assert_eq!(parsed.text, "🎉x");
+        assert_eq!(parsed.removed, 1);
+        assert_eq!(facet_spans(&parsed), vec!["x"]);
+    }
+
+    #[test]
+    fn sanitize_keeps_emoji_zwj_sequences() {
+        for emoji in [
+            "👨\u{200D}👩\u{200D}👧",
+            "❤\u{FE0F}\u{200D}🔥",
+            "🧑🏽\u{200D}💻",
+            "🏃\u{200D}♀\u{FE0F}",
+        ] {
+            let result = sanitize(emoji);
+            assert_eq!(result.text, emoji);
+            assert_eq!(result.removed, 0);
+        }
+    }
+
+    #[test]
+    fn sanitize_keeps_joiners_in_joining_scripts() {
+        // Persian ZWNJ and a Devanagari half form
+        for text in ["می\u{200C}خواهم", "क्\u{200D}ष"] {
+            let result = sanitize(text);
+            assert_eq!(result.text, text);
+            assert_eq!(result.removed, 0);
+        }
+    }
+
+    #[test]
+    fn sanitize_drops_joiners_interleaved_in_latin_text() {
+        let result = sanitize("s\u{200D}p\u{200C}a\u{200D}\u{200D}m \u{200D}");
+        assert_eq!(result.text, "spam ");
+        assert_eq!(result.removed, 5);
+    }
+
+    #[test]
+    fn sanitize_collapses_alternating_joiner_runs() {
+        let result = sanitize("👨\u{200D}\u{200C}\u{200D}👩 می\u{200C}\u{200D}\u{200C}خواهم");
+        assert_eq!(result.text, "👨\u{200D}👩 می\u{200C}خواهم");
+        assert_eq!(result.removed, 4);
+    }
+
+    #[test]
+    fn sanitize_strips_override_controls_and_bom() {
+        let result = sanitize("\u{FEFF}abc\u{202E}fdp.exe\u{202C}");
+        assert_eq!(result.text, "abcfdp.exe");
+        assert_eq!(result.removed, 3);
+    }
+
+    #[test]
+    fn sanitize_strips_control_characters_but_keeps_newlines_and_tabs() {
+        let result = sanitize("a\u{0007}b\tc\nd");
+        assert_eq!(result.text, "ab\tc\nd");
+        assert_eq!(result.removed, 1);
+    }
+
+    #[test]
+    fn sanitize_does_not_count_crlf_normalization() {
+        let result = sanitize("line one\r\nline two\r\n");
+        assert_eq!(result.text, "line one\nline two\n");
+        assert_eq!(result.removed, 0);
+    }
+
+    #[test]
+    fn sanitize_keeps_single_directional_marks() {
+        let result = sanitize("שלום\u{200F} abc");
+        assert_eq!(result.text, "שלום\u{200F} abc");
+        assert_eq!(result.removed, 0);
+
+        let result = sanitize("שלום\u{200F}\u{200F}\u{200E} abc");
+        assert_eq!(result.text, "שלום\u{200F} abc");
+        assert_eq!(result.removed, 2);
+    }
+
+    #[test]
+    fn tag_longer_than_limit_is_ignored() {
+        let long = format!("#{}", "a".repeat(MAX_TAG_LENGTH + 1));
+        assert!(detect_facets(&long).is_empty());
+    }
+}